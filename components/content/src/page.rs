@@ -7,6 +7,7 @@ use std::result::Result as StdResult;
 use tera::{Tera, Context as TeraContext};
 use serde::ser::{SerializeStruct, self};
 use slug::slugify;
+use chrono::prelude::*;
 
 use errors::{Result, ResultExt};
 use config::Config;
@@ -38,6 +39,12 @@ pub struct Page {
     pub path: String,
     /// The full URL for that page
     pub permalink: String,
+    /// The year of the date of the page, if any
+    pub year: Option<i32>,
+    /// The month of the date of the page, if any
+    pub month: Option<u32>,
+    /// The day of the date of the page, if any
+    pub day: Option<u32>,
     /// The summary for the article, defaults to None
     /// When <!-- more --> is found in the text, will take the content up to that part
     /// as summary
@@ -48,6 +55,13 @@ pub struct Page {
     pub next: Option<Box<Page>>,
     /// Toc made from the headers of the markdown file
     pub toc: Vec<Header>,
+    /// The ordered list of parent section permalinks, from the root down to the
+    /// page's immediate section. Populated once the content tree is assembled.
+    pub ancestors: Vec<String>,
+    /// The path to the source `.md` file, relative to the content directory.
+    /// Combined with the `edit_url_template` declared in `Config`, templates use
+    /// this to build "edit this page" links back to the source repository.
+    pub relative_path: String,
 }
 
 
@@ -64,10 +78,15 @@ impl Page {
             slug: "".to_string(),
             path: "".to_string(),
             permalink: "".to_string(),
+            year: None,
+            month: None,
+            day: None,
             summary: None,
             previous: None,
             next: None,
             toc: vec![],
+            ancestors: vec![],
+            relative_path: "".to_string(),
         }
     }
 
@@ -82,6 +101,33 @@ impl Page {
         let (meta, content) = split_page_content(file_path, content)?;
         let mut page = Page::new(file_path, meta);
         page.raw_content = content;
+
+        // Backward compatibility: the legacy `tags = [...]` and singular
+        // `category = "..."` front-matter fields are folded into the generic
+        // taxonomy map before anything else looks at it, so content written
+        // against the old fields keeps classifying unchanged. The singular
+        // `category` lands on the conventional plural `categories` axis (the
+        // default taxonomy name), which is why the key is pluralised here.
+        if let Some(tags) = page.meta.tags.take() {
+            page.meta.taxonomies.insert("tags".to_string(), tags);
+        }
+        if let Some(category) = page.meta.category.take() {
+            page.meta.taxonomies.insert("categories".to_string(), vec![category]);
+        }
+
+        // Every taxonomy axis used on a page must be declared in the config, so a
+        // typo like `tagz` is a hard error instead of being silently accepted and
+        // serialized. The legacy `tags`/`category` sugar is normalised into this
+        // same map upstream, so it is validated here too.
+        for key in page.meta.taxonomies.keys() {
+            if !config.taxonomies.iter().any(|t| &t.name == key) {
+                bail!(
+                    "Page `{}` has taxonomy `{}` which is not defined in the config",
+                    file_path.display(),
+                    key
+                );
+            }
+        }
         page.slug = {
             if let Some(ref slug) = page.meta.slug {
                 slug.trim().to_string()
@@ -98,15 +144,40 @@ impl Page {
             }
         };
 
+        // Decompose the date into its components so templates can build
+        // date archives and grouped listings. A missing or invalid date just
+        // leaves every component at `None`.
+        if let Some(ref date) = page.meta.date {
+            // Take the leading `YYYY-MM-DD` by characters, never by bytes, so a
+            // front-matter value whose 10th byte falls mid-codepoint can't panic.
+            let date_part: String = date.chars().take(10).collect();
+            if let Ok(parsed) = NaiveDate::parse_from_str(&date_part, "%Y-%m-%d") {
+                page.year = Some(parsed.year());
+                page.month = Some(parsed.month());
+                page.day = Some(parsed.day());
+            }
+        }
+
         if let Some(ref p) = page.meta.path {
             page.path = p.trim().trim_left_matches('/').to_string();
 
-        } else {
-            page.path = if page.file.components.is_empty() {
-                page.slug.clone()
+        } else if let Some(ref pattern) = config.permalink_pattern {
+            // A date-based permalink pattern (e.g. `{year}/{month}/{day}/{slug}`)
+            // only applies when the page actually has a valid date, otherwise we
+            // fall back to the slug-based path below.
+            if let (Some(year), Some(month), Some(day)) = (page.year, page.month, page.day) {
+                page.path = pattern
+                    .replace("{year}", &format!("{:04}", year))
+                    .replace("{month}", &format!("{:02}", month))
+                    .replace("{day}", &format!("{:02}", day))
+                    .replace("{slug}", &page.slug)
+                    .trim_left_matches('/')
+                    .to_string();
             } else {
-                format!("{}/{}", page.file.components.join("/"), page.slug)
-            };
+                page.path = Page::path_from_components(&page.file.components, &page.slug);
+            }
+        } else {
+            page.path = Page::path_from_components(&page.file.components, &page.slug);
         }
         if !page.path.ends_with('/') {
             page.path = format!("{}/", page.path);
@@ -114,9 +185,24 @@ impl Page {
 
         page.permalink = config.make_permalink(&page.path);
 
+        // The path to the source file relative to the content directory, so
+        // templates can link back to it (e.g. "edit on GitHub"). Reuse the
+        // canonical value `FileInfo` already computed rather than rebuilding it.
+        page.relative_path = page.file.relative.clone();
+
         Ok(page)
     }
 
+    /// Builds the slug-based path from the section components, used whenever no
+    /// explicit path or date-based pattern applies.
+    fn path_from_components(components: &[String], slug: &str) -> String {
+        if components.is_empty() {
+            slug.to_string()
+        } else {
+            format!("{}/{}", components.join("/"), slug)
+        }
+    }
+
     /// Read and parse a .md file into a Page struct
     pub fn from_file<P: AsRef<Path>>(path: P, config: &Config) -> Result<Page> {
         let path = path.as_ref();
@@ -156,6 +242,34 @@ impl Page {
         Ok(())
     }
 
+    /// The ancestors of a page can only be known once every section has been
+    /// parsed and their relationships resolved, so - like `render_markdown` -
+    /// this runs as a separate step on the assembled content tree.
+    ///
+    /// `section_permalinks` maps each section's content-relative path (the empty
+    /// string for the root section, `posts`, `posts/intro`, ...) to its
+    /// permalink. We walk from the root down the page's own path components,
+    /// collecting the permalink of every section we cross, so the result is the
+    /// ordered chain from root to the page's immediate section.
+    pub fn populate_ancestors(&mut self, section_permalinks: &HashMap<String, String>) {
+        let mut ancestors = vec![];
+        let mut current = String::new();
+        if let Some(permalink) = section_permalinks.get(&current) {
+            ancestors.push(permalink.clone());
+        }
+        for component in &self.file.components {
+            if current.is_empty() {
+                current = component.clone();
+            } else {
+                current = format!("{}/{}", current, component);
+            }
+            if let Some(permalink) = section_permalinks.get(&current) {
+                ancestors.push(permalink.clone());
+            }
+        }
+        self.ancestors = ancestors;
+    }
+
     /// Renders the page using the default layout, unless specified in front-matter
     pub fn render_html(&self, tera: &Tera, config: &Config) -> Result<String> {
         let tpl_name = match self.meta.template {
@@ -185,27 +299,40 @@ impl Default for Page {
             slug: "".to_string(),
             path: "".to_string(),
             permalink: "".to_string(),
+            year: None,
+            month: None,
+            day: None,
             summary: None,
             previous: None,
             next: None,
             toc: vec![],
+            ancestors: vec![],
+            relative_path: "".to_string(),
         }
     }
 }
 
 impl ser::Serialize for Page {
     fn serialize<S>(&self, serializer: S) -> StdResult<S::Ok, S::Error> where S: ser::Serializer {
-        let mut state = serializer.serialize_struct("page", 16)?;
+        let mut state = serializer.serialize_struct("page", 23)?;
         state.serialize_field("content", &self.content)?;
         state.serialize_field("title", &self.meta.title)?;
         state.serialize_field("description", &self.meta.description)?;
         state.serialize_field("date", &self.meta.date)?;
+        state.serialize_field("year", &self.year)?;
+        state.serialize_field("month", &self.month)?;
+        state.serialize_field("day", &self.day)?;
         state.serialize_field("slug", &self.slug)?;
         state.serialize_field("path", &self.path)?;
         state.serialize_field("permalink", &self.permalink)?;
         state.serialize_field("summary", &self.summary)?;
-        state.serialize_field("tags", &self.meta.tags)?;
-        state.serialize_field("category", &self.meta.category)?;
+        state.serialize_field("taxonomies", &self.meta.taxonomies)?;
+        // `tags`/`category` are kept as sugar over the generic taxonomy map so
+        // templates and feeds written against the old fields keep working. The
+        // singular legacy `category` maps onto the conventional plural
+        // `categories` axis (its first term), matching the default taxonomy name.
+        state.serialize_field("tags", &self.meta.taxonomies.get("tags"))?;
+        state.serialize_field("category", &self.meta.taxonomies.get("categories").and_then(|terms| terms.get(0)))?;
         state.serialize_field("extra", &self.meta.extra)?;
         let (word_count, reading_time) = get_reading_analytics(&self.raw_content);
         state.serialize_field("word_count", &word_count)?;
@@ -213,6 +340,9 @@ impl ser::Serialize for Page {
         state.serialize_field("previous", &self.previous)?;
         state.serialize_field("next", &self.next)?;
         state.serialize_field("toc", &self.toc)?;
+        state.serialize_field("ancestors", &self.ancestors)?;
+        state.serialize_field("relative_path", &self.relative_path)?;
+        state.serialize_field("components", &self.file.components)?;
         state.end()
     }
 }
@@ -226,11 +356,16 @@ mod tests {
 
     use tera::Tera;
     use tempdir::TempDir;
+    use serde_json;
 
-    use config::Config;
+    use config::{Config, Taxonomy};
     use super::Page;
     use front_matter::InsertAnchor;
 
+    fn taxonomy(name: &str) -> Taxonomy {
+        Taxonomy { name: name.to_string(), ..Taxonomy::default() }
+    }
+
 
     #[test]
     fn test_can_parse_a_valid_page() {
@@ -313,6 +448,72 @@ Hello world"#;
         assert_eq!(page.permalink, config.make_permalink("hello-world"));
     }
 
+    #[test]
+    fn extracts_date_components() {
+        let content = r#"
++++
+title = "Hello"
+date = "2018-01-05"
++++
+Hello world"#;
+        let res = Page::parse(Path::new("post.md"), content, &Config::default());
+        assert!(res.is_ok());
+        let page = res.unwrap();
+        assert_eq!(page.year, Some(2018));
+        assert_eq!(page.month, Some(1));
+        assert_eq!(page.day, Some(5));
+    }
+
+    #[test]
+    fn short_or_invalid_date_leaves_components_empty() {
+        // A too-short, multi-byte date must neither panic nor yield components.
+        let content = r#"
++++
+title = "Hello"
+date = "２０１８"
++++
+Hello world"#;
+        let res = Page::parse(Path::new("post.md"), content, &Config::default());
+        assert!(res.is_ok());
+        let page = res.unwrap();
+        assert_eq!(page.year, None);
+        assert_eq!(page.month, None);
+        assert_eq!(page.day, None);
+    }
+
+    #[test]
+    fn builds_date_based_path_from_permalink_pattern() {
+        let content = r#"
++++
+title = "Hello"
+date = "2018-01-05"
+slug = "hello-world"
++++
+Hello world"#;
+        let mut config = Config::default();
+        config.permalink_pattern = Some("{year}/{month}/{day}/{slug}".to_string());
+        let res = Page::parse(Path::new("post.md"), content, &config);
+        assert!(res.is_ok());
+        let page = res.unwrap();
+        assert_eq!(page.path, "2018/01/05/hello-world/");
+    }
+
+    #[test]
+    fn falls_back_to_slug_path_when_pattern_needs_a_missing_date() {
+        let content = r#"
++++
+title = "Hello"
+slug = "hello-world"
++++
+Hello world"#;
+        let mut config = Config::default();
+        config.permalink_pattern = Some("{year}/{month}/{day}/{slug}".to_string());
+        let res = Page::parse(Path::new("post.md"), content, &config);
+        assert!(res.is_ok());
+        let page = res.unwrap();
+        assert_eq!(page.path, "hello-world/");
+    }
+
     #[test]
     fn errors_on_invalid_front_matter_format() {
         // missing starting +++
@@ -336,6 +537,113 @@ Hello world"#;
         assert_eq!(page.permalink, config.make_permalink(&page.slug));
     }
 
+    #[test]
+    fn serializes_taxonomies_with_tags_and_category_sugar() {
+        let content = r#"
++++
+title = "Hello"
+[taxonomies]
+tags = ["rust", "web"]
+categories = ["programming"]
++++
+Hello world"#;
+        let mut config = Config::default();
+        config.taxonomies = vec![taxonomy("tags"), taxonomy("categories")];
+        let res = Page::parse(Path::new("post.md"), content, &config);
+        assert!(res.is_ok());
+        let page = res.unwrap();
+        let value = serde_json::to_value(&page).unwrap();
+        assert_eq!(value["taxonomies"]["tags"][0], "rust");
+        // The old fields stay available as sugar over the generic map.
+        assert_eq!(value["tags"][0], "rust");
+        assert_eq!(value["category"], "programming");
+    }
+
+    #[test]
+    fn legacy_tags_and_category_front_matter_land_in_taxonomies() {
+        // Content authored with the old top-level fields must keep working.
+        let content = r#"
++++
+title = "Hello"
+tags = ["rust", "web"]
+category = "programming"
++++
+Hello world"#;
+        let mut config = Config::default();
+        config.taxonomies = vec![taxonomy("tags"), taxonomy("categories")];
+        let res = Page::parse(Path::new("post.md"), content, &config);
+        assert!(res.is_ok());
+        let page = res.unwrap();
+        assert_eq!(page.meta.taxonomies.get("tags").unwrap(), &vec!["rust".to_string(), "web".to_string()]);
+        assert_eq!(page.meta.taxonomies.get("categories").unwrap(), &vec!["programming".to_string()]);
+        let value = serde_json::to_value(&page).unwrap();
+        assert_eq!(value["tags"][0], "rust");
+        assert_eq!(value["category"], "programming");
+    }
+
+    #[test]
+    fn errors_on_undeclared_taxonomy() {
+        let content = r#"
++++
+title = "Hello"
+[taxonomies]
+tagz = ["oops"]
++++
+Hello world"#;
+        let mut config = Config::default();
+        config.taxonomies = vec![taxonomy("tags")];
+        let res = Page::parse(Path::new("post.md"), content, &config);
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn populate_ancestors_walks_the_section_tree() {
+        let res = Page::parse(Path::new("content/posts/intro/start.md"), "+++\n+++", &Config::default());
+        assert!(res.is_ok());
+        let mut page = res.unwrap();
+        assert!(page.ancestors.is_empty());
+
+        // The resolved section tree, keyed by content-relative section path.
+        let mut sections = HashMap::new();
+        sections.insert("".to_string(), "http://a.com/".to_string());
+        sections.insert("posts".to_string(), "http://a.com/posts/".to_string());
+        sections.insert("posts/intro".to_string(), "http://a.com/posts/intro/".to_string());
+        // A sibling section must not leak into the chain.
+        sections.insert("other".to_string(), "http://a.com/other/".to_string());
+
+        page.populate_ancestors(&sections);
+        assert_eq!(
+            page.ancestors,
+            vec![
+                "http://a.com/".to_string(),
+                "http://a.com/posts/".to_string(),
+                "http://a.com/posts/intro/".to_string(),
+            ]
+        );
+        let value = serde_json::to_value(&page).unwrap();
+        assert_eq!(value["ancestors"][0], "http://a.com/");
+    }
+
+    #[test]
+    fn serializes_relative_path_and_components() {
+        let tmp_dir = TempDir::new("example").expect("create temp dir");
+        let path = tmp_dir.path();
+        create_dir(&path.join("content")).expect("create content temp dir");
+        create_dir(&path.join("content").join("posts")).expect("create posts temp dir");
+        let mut f = File::create(path.join("content").join("posts").join("start.md")).unwrap();
+        f.write_all(b"+++\n+++\n").unwrap();
+
+        let res = Page::from_file(
+            path.join("content").join("posts").join("start.md").as_path(),
+            &Config::default()
+        );
+        assert!(res.is_ok());
+        let page = res.unwrap();
+        let value = serde_json::to_value(&page).unwrap();
+        assert_eq!(value["relative_path"], "posts/start.md");
+        assert_eq!(value["components"][0], "posts");
+    }
+
     #[test]
     fn can_specify_summary() {
         let config = Config::default();